@@ -0,0 +1,67 @@
+/// Synchronization type carried in bits 2-3 of an isochronous endpoint's `bmAttributes`.
+#[repr(u8)]
+#[derive(Eq, PartialEq, Debug, Copy, Clone, Hash)]
+pub enum IsochronousSynchronizationType {
+    /// No synchronization is provided.
+    NoSynchronization = 0b00,
+    /// Unsynchronized, but the source/sink free-runs and is adjusted asynchronously.
+    Asynchronous = 0b01,
+    /// Synchronized against the USB bus clock, with no feedback.
+    Adaptive = 0b10,
+    /// Synchronized against an external clock or the bus clock, with feedback.
+    Synchronous = 0b11,
+}
+
+/// Usage type carried in bits 4-5 of an isochronous endpoint's `bmAttributes`.
+#[repr(u8)]
+#[derive(Eq, PartialEq, Debug, Copy, Clone, Hash)]
+pub enum IsochronousUsageType {
+    /// Carries the data of interest.
+    Data = 0b00,
+    /// Carries explicit feedback information for another isochronous endpoint.
+    Feedback = 0b01,
+    /// Carries both data and implicit feedback information.
+    ImplicitFeedbackData = 0b10,
+}
+
+/// The transfer type used for an endpoint.
+#[derive(Eq, PartialEq, Debug, Copy, Clone, Hash)]
+pub enum EndpointType {
+    /// Control endpoint.
+    Control,
+    /// Isochronous endpoint, with its synchronization and usage type.
+    Isochronous {
+        synchronization: IsochronousSynchronizationType,
+        usage: IsochronousUsageType,
+    },
+    /// Bulk endpoint.
+    Bulk,
+    /// Interrupt endpoint.
+    Interrupt,
+}
+
+impl EndpointType {
+    /// Encodes the transfer type bits (0-1), and for isochronous endpoints the synchronization
+    /// (2-3) and usage (4-5) bits, of the endpoint descriptor's `bmAttributes` field.
+    pub fn bm_attributes(&self) -> u8 {
+        match self {
+            EndpointType::Control => 0b00,
+            EndpointType::Isochronous { synchronization, usage } => {
+                0b01 | ((*synchronization as u8) << 2) | ((*usage as u8) << 4)
+            }
+            EndpointType::Bulk => 0b10,
+            EndpointType::Interrupt => 0b11,
+        }
+    }
+}
+
+/// An isochronous endpoint's maximum packet size is additionally bounded by its polling
+/// interval (`bInterval`) - a full-speed endpoint may move at most `max_packet_size` bytes
+/// once per frame (1ms), so a larger `bInterval` must scale the allowed payload down.
+pub fn iso_max_packet_size_for_interval(max_packet_size: u16, b_interval: u8) -> u16 {
+    if b_interval == 0 {
+        max_packet_size
+    } else {
+        max_packet_size / (b_interval as u16)
+    }
+}
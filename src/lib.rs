@@ -0,0 +1,7 @@
+//! Device-side USB stack for `usb-device`.
+
+pub mod endpoint;
+pub mod test_class;
+
+#[cfg(feature = "msos-descriptors")]
+pub mod msos;
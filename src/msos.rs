@@ -0,0 +1,121 @@
+//! Microsoft OS 2.0 descriptors, gated behind the `msos-descriptors` feature.
+//!
+//! These let Windows auto-bind WinUSB to a device without requiring a signed INF, by exposing a
+//! BOS platform capability descriptor that points the host at a vendor-request-retrievable
+//! descriptor set (MS OS 2.0 Descriptor Specification).
+
+#![cfg(feature = "msos-descriptors")]
+
+use crate::descriptor::BosWriter;
+
+/// `{D8DD60DF-4589-4CC7-9CD2-659D9E648A9F}`, the platform capability UUID Windows looks for to
+/// recognize an MS OS 2.0 descriptor set.
+pub const MS_OS_20_UUID: [u8; 16] = [
+    0xDF, 0x60, 0xDD, 0xD8, 0x89, 0x45, 0xC7, 0x4C, 0x9C, 0xD2, 0x65, 0x9D, 0x9E, 0x64, 0x8A, 0x9F,
+];
+
+const MS_OS_20_SET_HEADER_DESCRIPTOR: u16 = 0x00;
+const MS_OS_20_SUBSET_HEADER_CONFIGURATION: u16 = 0x01;
+const MS_OS_20_SUBSET_HEADER_FUNCTION: u16 = 0x02;
+const MS_OS_20_FEATURE_COMPATIBLE_ID: u16 = 0x03;
+const MS_OS_20_FEATURE_REG_PROPERTY: u16 = 0x04;
+
+const WINDOWS_VERSION_8_1: u32 = 0x06_03_00_00;
+
+/// Registry property data types, as used in the `wPropertyDataType` field of a registry
+/// property feature descriptor.
+#[repr(u16)]
+#[derive(Eq, PartialEq, Debug, Copy, Clone)]
+pub enum PropertyDataType {
+    Sz = 1,
+    MultiSz = 7,
+}
+
+/// Builds the vendor-retrievable MS OS 2.0 descriptor set: a set header, a compatible ID feature
+/// descriptor, and a registry property feature descriptor.
+pub struct MsOsDescriptorSet {
+    compatible_id: [u8; 8],
+    property_name: &'static [u8],
+    property_data: &'static [u8],
+    property_data_type: PropertyDataType,
+}
+
+impl MsOsDescriptorSet {
+    pub const fn new(
+        compatible_id: [u8; 8],
+        property_name: &'static [u8],
+        property_data: &'static [u8],
+        property_data_type: PropertyDataType,
+    ) -> Self {
+        MsOsDescriptorSet { compatible_id, property_name, property_data, property_data_type }
+    }
+
+    /// Total length, in bytes, of the descriptor set this builds - the value reported as
+    /// `wTotalLength` in both the BOS platform capability and the set header itself.
+    pub fn total_length(&self) -> u16 {
+        let header = 10;
+        // wLength + wDescriptorType + CompatibleID + SubCompatibleID (reserved, zero-filled).
+        let compatible_id = 4 + 8 + 8;
+        let registry_property = 4 + 2 + 2 + self.property_name.len() + 2 + self.property_data.len();
+
+        (header + compatible_id + registry_property) as u16
+    }
+
+    /// Writes the descriptor set to `buf`, returning the number of bytes written.
+    pub fn write(&self, buf: &mut [u8]) -> usize {
+        let total_length = self.total_length();
+        let mut offset = 0;
+
+        buf[0..2].copy_from_slice(&10u16.to_le_bytes());
+        buf[2..4].copy_from_slice(&MS_OS_20_SET_HEADER_DESCRIPTOR.to_le_bytes());
+        buf[4..8].copy_from_slice(&WINDOWS_VERSION_8_1.to_le_bytes());
+        buf[8..10].copy_from_slice(&total_length.to_le_bytes());
+        offset += 10;
+
+        let compatible_id_len = 4 + 8 + 8;
+        buf[offset..offset + 2].copy_from_slice(&(compatible_id_len as u16).to_le_bytes());
+        buf[offset + 2..offset + 4].copy_from_slice(&MS_OS_20_FEATURE_COMPATIBLE_ID.to_le_bytes());
+        buf[offset + 4..offset + 12].copy_from_slice(&self.compatible_id);
+        buf[offset + 12..offset + 20].copy_from_slice(&[0u8; 8]);
+        offset += compatible_id_len;
+
+        let registry_property_len =
+            4 + 2 + 2 + self.property_name.len() + 2 + self.property_data.len();
+        buf[offset..offset + 2].copy_from_slice(&(registry_property_len as u16).to_le_bytes());
+        buf[offset + 2..offset + 4].copy_from_slice(&MS_OS_20_FEATURE_REG_PROPERTY.to_le_bytes());
+        buf[offset + 4..offset + 6].copy_from_slice(&(self.property_data_type as u16).to_le_bytes());
+        buf[offset + 6..offset + 8].copy_from_slice(&(self.property_name.len() as u16).to_le_bytes());
+        offset += 8;
+        buf[offset..offset + self.property_name.len()].copy_from_slice(self.property_name);
+        offset += self.property_name.len();
+        buf[offset..offset + 2].copy_from_slice(&(self.property_data.len() as u16).to_le_bytes());
+        offset += 2;
+        buf[offset..offset + self.property_data.len()].copy_from_slice(self.property_data);
+        offset += self.property_data.len();
+
+        offset
+    }
+
+    /// Writes the platform capability descriptor referencing this set into a device's BOS
+    /// descriptor, given the vendor request code the host should use to fetch the set.
+    ///
+    /// `bAltEnumCode` is left at `0`, meaning the device does not support the alternate
+    /// enumeration mechanism described by the MS OS 2.0 spec.
+    pub fn write_platform_capability(
+        &self,
+        writer: &mut BosWriter,
+        vendor_code: u8,
+    ) -> crate::Result<()> {
+        // reserved(1) + UUID(16) + dwWindowsVersion(4) + wTotalLength(2) + bMS_VendorCode(1)
+        // + bAltEnumCode(1).
+        let mut data = [0u8; 25];
+        data[0] = 0;
+        data[1..17].copy_from_slice(&MS_OS_20_UUID);
+        data[17..21].copy_from_slice(&WINDOWS_VERSION_8_1.to_le_bytes());
+        data[21..23].copy_from_slice(&self.total_length().to_le_bytes());
+        data[23] = vendor_code;
+        data[24] = 0;
+
+        writer.capability_platform(&data)
+    }
+}
@@ -0,0 +1,394 @@
+//! A test USB device class for testing USB driver implementations. Test this against different
+//! operating systems to catch regressions in device-side endpoint and control handling.
+
+use crate::bus::{PollResult, UsbBusAllocator};
+use crate::class_prelude::*;
+use crate::control;
+use crate::endpoint::{EndpointType, IsochronousSynchronizationType, IsochronousUsageType};
+#[cfg(feature = "msos-descriptors")]
+use crate::msos::{MsOsDescriptorSet, PropertyDataType};
+
+pub const PRODUCT: &str = "Test Product";
+pub const MANUFACTURER: &str = "Test Manufacturer";
+pub const SERIAL_NUMBER: &str = "TEST_SERIAL";
+pub const CUSTOM_STRING: &str = "Custom String";
+pub const INTERFACE_DESCRIPTION: &str = "Test Interface";
+
+pub const REQ_STORE_REQUEST: u8 = 1;
+pub const REQ_READ_BUFFER: u8 = 2;
+pub const REQ_WRITE_BUFFER: u8 = 3;
+pub const REQ_READ_LONG_DATA: u8 = 4;
+pub const REQ_UNKNOWN: u8 = 5;
+pub const REQ_SET_BENCH_ENABLED: u8 = 6;
+
+/// Opt-in USBTMC-style bulk framing. Disabled by default so `bulk_loopback` keeps exercising
+/// raw loopback semantics.
+pub const REQ_SET_USBTMC_ENABLED: u8 = 7;
+
+/// Arms (non-zero `wValue`) or disarms (zero) remote wakeup; once armed, the device signals a
+/// resume as soon as it next observes the bus entering suspend, and the arming is then cleared.
+pub const REQ_ARM_REMOTE_WAKEUP: u8 = 8;
+
+/// Reads back how many times the device has observed the bus entering suspend since power-up,
+/// i.e. one count per suspend/resume cycle.
+pub const REQ_READ_SUSPEND_COUNT: u8 = 9;
+
+/// `bMS_VendorCode`: the vendor request the host uses to fetch the MS OS 2.0 descriptor set
+/// named in the device's BOS platform capability descriptor.
+#[cfg(feature = "msos-descriptors")]
+pub const MS_OS_VENDOR_CODE: u8 = 0x20;
+
+#[cfg(feature = "msos-descriptors")]
+pub const MS_OS_20_COMPATIBLE_ID: &[u8; 8] = b"WINUSB\0\0";
+
+#[cfg(feature = "msos-descriptors")]
+pub const MS_OS_20_REGISTRY_PROPERTY_NAME: &[u8] = &[
+    b'D', 0, b'e', 0, b'v', 0, b'i', 0, b'c', 0, b'e', 0, b'I', 0, b'n', 0, b't', 0, b'e', 0,
+    b'r', 0, b'f', 0, b'a', 0, b'c', 0, b'e', 0, b'G', 0, b'U', 0, b'I', 0, b'D', 0, 0, 0,
+];
+
+#[cfg(feature = "msos-descriptors")]
+pub const MS_OS_20_REGISTRY_PROPERTY_DATA: &[u8] = &[
+    b'{', 0, b'9', 0, b'1', 0, b'8', 0, b'5', 0, b'6', 0, b'6', 0, b'1', 0, b'-', 0, b'3', 0,
+    b'f', 0, b'3', 0, b'e', 0, b'-', 0, b'4', 0, b'd', 0, b'c', 0, b'1', 0, b'-', 0, b'8', 0,
+    b'9', 0, b'5', 0, b'7', 0, b'-', 0, b'8', 0, b'a', 0, b'a', 0, b'f', 0, b'a', 0, b'8', 0,
+    b'5', 0, b'6', 0, b'4', 0, b'1', 0, b'3', 0, b'a', 0, b'}', 0, 0, 0,
+];
+
+pub const LONG_DATA: &[u8] = &[0x17; 257];
+
+const STORE_REQUEST_BUFFER_LEN: usize = 8;
+
+const USBTMC_PAYLOAD_LEN: usize = 512;
+const USBTMC_REPLY_BUF_LEN: usize = 12 + USBTMC_PAYLOAD_LEN + 3;
+
+/// Isochronous endpoints move 256-byte packets once per (micro)frame, large enough to catch
+/// double-buffering regressions without needing a full-speed high-bandwidth endpoint.
+pub const ISO_PACKET_SIZE: u16 = 256;
+
+/// A USB test class for testing USB driver implementations.
+pub struct TestClass<'a, B: UsbBus> {
+    custom_string: StringIndex,
+    interface_string: StringIndex,
+    iface: InterfaceNumber,
+    ep_bulk_in: EndpointIn<'a, B>,
+    ep_bulk_out: EndpointOut<'a, B>,
+    ep_interrupt_in: EndpointIn<'a, B>,
+    ep_interrupt_out: EndpointOut<'a, B>,
+    ep_iso_in: EndpointIn<'a, B>,
+    ep_iso_out: EndpointOut<'a, B>,
+    control_buf: [u8; 256],
+    bench_enabled: bool,
+
+    store_request_buf: [u8; STORE_REQUEST_BUFFER_LEN],
+    write_buf: [u8; 257],
+    write_buf_len: usize,
+
+    usbtmc_enabled: bool,
+    usbtmc_tag: u8,
+    usbtmc_payload: heapless::Vec<u8, USBTMC_PAYLOAD_LEN>,
+
+    remote_wakeup_armed: bool,
+    suspend_count: u32,
+    suspended: bool,
+
+    // This test harness has no separate `UsbDevice` wrapper polling the bus, so TestClass is the
+    // bus's sole poller and is responsible for noticing suspend/resume transitions itself.
+    bus: &'a B,
+
+    #[cfg(feature = "msos-descriptors")]
+    msos_descriptor_set: MsOsDescriptorSet,
+}
+
+impl<B: UsbBus> TestClass<'_, B> {
+    /// Creates a new `TestClass`.
+    pub fn new(alloc: &UsbBusAllocator<B>) -> TestClass<'_, B> {
+        TestClass {
+            custom_string: alloc.string(),
+            interface_string: alloc.string(),
+            iface: alloc.interface(),
+            ep_bulk_in: alloc.bulk(64),
+            ep_bulk_out: alloc.bulk(64),
+            ep_interrupt_in: alloc.interrupt(31, 1),
+            ep_interrupt_out: alloc.interrupt(31, 1),
+            ep_iso_in: alloc.alloc(
+                None,
+                EndpointType::Isochronous {
+                    synchronization: IsochronousSynchronizationType::Asynchronous,
+                    usage: IsochronousUsageType::Data,
+                },
+                ISO_PACKET_SIZE,
+                1,
+            ).expect("alloc iso in endpoint"),
+            ep_iso_out: alloc.alloc(
+                None,
+                EndpointType::Isochronous {
+                    synchronization: IsochronousSynchronizationType::Asynchronous,
+                    usage: IsochronousUsageType::Data,
+                },
+                ISO_PACKET_SIZE,
+                1,
+            ).expect("alloc iso out endpoint"),
+            control_buf: [0; 256],
+            bench_enabled: false,
+
+            store_request_buf: [0; STORE_REQUEST_BUFFER_LEN],
+            write_buf: [0; 257],
+            write_buf_len: 0,
+
+            usbtmc_enabled: false,
+            usbtmc_tag: 0,
+            usbtmc_payload: heapless::Vec::new(),
+
+            remote_wakeup_armed: false,
+            suspend_count: 0,
+            suspended: false,
+            bus: alloc.bus(),
+
+            #[cfg(feature = "msos-descriptors")]
+            msos_descriptor_set: MsOsDescriptorSet::new(
+                *MS_OS_20_COMPATIBLE_ID,
+                MS_OS_20_REGISTRY_PROPERTY_NAME,
+                MS_OS_20_REGISTRY_PROPERTY_DATA,
+                PropertyDataType::Sz,
+            ),
+        }
+    }
+
+    /// Called from `poll()` whenever it observes the bus transition into or out of suspend.
+    fn on_bus_suspend_state_change(&mut self, suspended: bool) {
+        if suspended {
+            self.suspend_count += 1;
+
+            if self.remote_wakeup_armed {
+                self.remote_wakeup_armed = false;
+                // Asserting remote wakeup at the hardware level is just driving the bus back out
+                // of suspend - the same call the driver layer itself uses on host-initiated resume.
+                self.bus.resume();
+            }
+        }
+    }
+
+    fn handle_usbtmc_bulk_out(&mut self, data: &[u8]) {
+        const DEV_DEP_MSG_OUT: u8 = 1;
+        const REQUEST_DEV_DEP_MSG_IN: u8 = 2;
+        const USBTMC_HEADER_LEN: usize = 12;
+
+        if data.len() < USBTMC_HEADER_LEN {
+            return;
+        }
+
+        let msg_id = data[0];
+        let tag = data[1];
+        let transfer_size = u32::from_le_bytes(data[4..8].try_into().unwrap()) as usize;
+
+        match msg_id {
+            DEV_DEP_MSG_OUT => {
+                self.usbtmc_tag = tag;
+                let payload = &data[USBTMC_HEADER_LEN..];
+                let take = payload.len().min(transfer_size.saturating_sub(self.usbtmc_payload.len()));
+                let _ = self.usbtmc_payload.extend_from_slice(&payload[..take]);
+            }
+            REQUEST_DEV_DEP_MSG_IN => {
+                self.usbtmc_tag = tag;
+            }
+            _ => {}
+        }
+    }
+
+    // 12-byte header + the full usbtmc_payload capacity + up to 3 padding bytes, rounded up to a
+    // 4-byte boundary, so a full payload can never be truncated when building the reply.
+    fn usbtmc_reply_packet(&self) -> heapless::Vec<u8, USBTMC_REPLY_BUF_LEN> {
+        let mut reply = heapless::Vec::new();
+        let len = self.usbtmc_payload.len() as u32;
+
+        let _ = reply.push(2); // DEV_DEP_MSG_IN
+        let _ = reply.push(self.usbtmc_tag);
+        let _ = reply.push(!self.usbtmc_tag);
+        let _ = reply.push(0);
+        let _ = reply.extend_from_slice(&len.to_le_bytes());
+        let _ = reply.push(0x01); // EOM
+        let _ = reply.extend_from_slice(&[0, 0, 0]);
+        let _ = reply.extend_from_slice(&self.usbtmc_payload);
+
+        while reply.len() % 4 != 0 {
+            let _ = reply.push(0);
+        }
+
+        reply
+    }
+}
+
+impl<B: UsbBus> UsbClass<B> for TestClass<'_, B> {
+    fn get_configuration_descriptors(&self, writer: &mut DescriptorWriter) -> Result<()> {
+        writer.interface_alt(
+            self.iface,
+            0,
+            0xff,
+            0x00,
+            0x00,
+            Some(self.interface_string),
+        )?;
+
+        writer.endpoint(&self.ep_bulk_in)?;
+        writer.endpoint(&self.ep_bulk_out)?;
+        writer.endpoint(&self.ep_interrupt_in)?;
+        writer.endpoint(&self.ep_interrupt_out)?;
+        writer.endpoint(&self.ep_iso_in)?;
+        writer.endpoint(&self.ep_iso_out)?;
+
+        Ok(())
+    }
+
+    #[cfg(feature = "msos-descriptors")]
+    fn get_bos_descriptors(&self, writer: &mut BosWriter) -> Result<()> {
+        self.msos_descriptor_set.write_platform_capability(writer, MS_OS_VENDOR_CODE)
+    }
+
+    fn get_string(&self, index: StringIndex, lang_id: u16) -> Option<&str> {
+        if lang_id == 0x0409 && index == self.custom_string {
+            Some(CUSTOM_STRING)
+        } else if lang_id == 0x0409 && index == self.interface_string {
+            Some(INTERFACE_DESCRIPTION)
+        } else {
+            None
+        }
+    }
+
+    fn reset(&mut self) {
+        self.bench_enabled = false;
+        self.usbtmc_enabled = false;
+        self.usbtmc_payload.clear();
+    }
+
+    fn poll(&mut self) {
+        match self.bus.poll() {
+            PollResult::Suspend if !self.suspended => {
+                self.suspended = true;
+                self.on_bus_suspend_state_change(true);
+            }
+            PollResult::Resume | PollResult::Reset if self.suspended => {
+                self.suspended = false;
+                self.on_bus_suspend_state_change(false);
+            }
+            _ => {}
+        }
+
+        if self.suspended {
+            return;
+        }
+
+        if self.bench_enabled {
+            // Bench mode sinks everything on bulk OUT and keeps bulk IN supplied with full
+            // packets, so both write and read benchmarks have a peer.
+            let _ = self.ep_bulk_out.read(&mut self.control_buf);
+            let packet_len = self.ep_bulk_in.max_packet_size() as usize;
+            let _ = self.ep_bulk_in.write(&self.control_buf[..packet_len]);
+
+            return;
+        }
+
+        if self.usbtmc_enabled {
+            if let Ok(count) = self.ep_bulk_out.read(&mut self.control_buf) {
+                // Copy out of control_buf into a stack-local buffer so handle_usbtmc_bulk_out can
+                // take &mut self without the borrow checker seeing an overlapping borrow of
+                // control_buf - no_std has no allocator to fall back on a Vec for this.
+                let mut packet = [0u8; 256];
+                packet[..count].copy_from_slice(&self.control_buf[..count]);
+                let is_request_in = packet[..count].first() == Some(&2);
+
+                self.handle_usbtmc_bulk_out(&packet[..count]);
+
+                if is_request_in {
+                    let reply = self.usbtmc_reply_packet();
+                    let _ = self.ep_bulk_in.write(&reply);
+                }
+            }
+
+            return;
+        }
+
+        if let Ok(count) = self.ep_bulk_out.read(&mut self.control_buf) {
+            let _ = self.ep_bulk_in.write(&self.control_buf[..count]);
+        }
+
+        if let Ok(count) = self.ep_interrupt_out.read(&mut self.control_buf) {
+            let _ = self.ep_interrupt_in.write(&self.control_buf[..count]);
+        }
+
+        if let Ok(count) = self.ep_iso_out.read(&mut self.control_buf) {
+            let _ = self.ep_iso_in.write(&self.control_buf[..count]);
+        }
+    }
+
+    fn control_in(&mut self, xfer: ControlIn<B>) {
+        let req = xfer.request();
+
+        if !(req.request_type == control::RequestType::Vendor
+            && req.recipient == control::Recipient::Device)
+        {
+            return;
+        }
+
+        match req.request {
+            REQ_READ_BUFFER => {
+                xfer.accept_with(&self.store_request_buf).ok();
+            }
+            REQ_READ_LONG_DATA => {
+                xfer.accept_with_static(LONG_DATA).ok();
+            }
+            REQ_READ_SUSPEND_COUNT => {
+                xfer.accept_with(&self.suspend_count.to_le_bytes()).ok();
+            }
+            #[cfg(feature = "msos-descriptors")]
+            req if req == MS_OS_VENDOR_CODE && xfer.request().index == 7 => {
+                let mut buf = [0u8; 256];
+                let len = self.msos_descriptor_set.write(&mut buf);
+                xfer.accept_with(&buf[..len]).ok();
+            }
+            _ => {}
+        }
+    }
+
+    fn control_out(&mut self, xfer: ControlOut<B>) {
+        let req = xfer.request();
+
+        if !(req.request_type == control::RequestType::Vendor
+            && req.recipient == control::Recipient::Device)
+        {
+            return;
+        }
+
+        match req.request {
+            REQ_STORE_REQUEST => {
+                let data = xfer.data();
+                self.store_request_buf[0] = (0x02u8) << 5;
+                self.store_request_buf[1] = REQ_STORE_REQUEST;
+                self.store_request_buf[2..4].copy_from_slice(&req.value.to_le_bytes());
+                self.store_request_buf[4..6].copy_from_slice(&req.index.to_le_bytes());
+                self.store_request_buf[6..8].copy_from_slice(&(data.len() as u16).to_le_bytes());
+                xfer.accept().ok();
+            }
+            REQ_WRITE_BUFFER => {
+                let data = xfer.data();
+                self.write_buf[..data.len()].copy_from_slice(data);
+                self.write_buf_len = data.len();
+                xfer.accept().ok();
+            }
+            REQ_SET_BENCH_ENABLED => {
+                self.bench_enabled = xfer.request().value != 0;
+                xfer.accept().ok();
+            }
+            REQ_SET_USBTMC_ENABLED => {
+                self.usbtmc_enabled = xfer.request().value != 0;
+                self.usbtmc_payload.clear();
+                xfer.accept().ok();
+            }
+            REQ_ARM_REMOTE_WAKEUP => {
+                self.remote_wakeup_armed = xfer.request().value != 0;
+                xfer.accept().ok();
+            }
+            _ => {}
+        }
+    }
+}
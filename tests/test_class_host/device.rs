@@ -0,0 +1,196 @@
+use libusb::*;
+use std::time::Duration;
+
+pub const TIMEOUT: Duration = Duration::from_secs(1);
+
+pub struct DeviceHandles<'a> {
+    pub context: &'a Context,
+    pub handle: DeviceHandle<'a>,
+    pub device_descriptor: DeviceDescriptor,
+    pub config_descriptor: ConfigDescriptor,
+    pub en_us: Language,
+
+    pub ep_bulk_in: u8,
+    pub ep_bulk_out: u8,
+    pub ep_bulk_out_max_packet_size: u16,
+    pub ep_interrupt_in: u8,
+    pub ep_interrupt_out: u8,
+    pub ep_iso_in: u8,
+    pub ep_iso_out: u8,
+    pub iso_packet_size: u16,
+}
+
+impl<'a> DeviceHandles<'a> {
+    pub fn read_control(
+        &self,
+        request_type: u8,
+        request: u8,
+        value: u16,
+        index: u16,
+        buf: &mut [u8],
+        timeout: Duration,
+    ) -> libusb::Result<usize> {
+        self.handle.read_control(request_type, request, value, index, buf, timeout)
+    }
+
+    pub fn write_control(
+        &self,
+        request_type: u8,
+        request: u8,
+        value: u16,
+        index: u16,
+        buf: &[u8],
+        timeout: Duration,
+    ) -> libusb::Result<usize> {
+        self.handle.write_control(request_type, request, value, index, buf, timeout)
+    }
+
+    pub fn read_bulk(&self, endpoint: u8, buf: &mut [u8], timeout: Duration) -> libusb::Result<usize> {
+        self.handle.read_bulk(endpoint, buf, timeout)
+    }
+
+    pub fn write_bulk(&self, endpoint: u8, buf: &[u8], timeout: Duration) -> libusb::Result<usize> {
+        self.handle.write_bulk(endpoint, buf, timeout)
+    }
+
+    pub fn read_interrupt(&self, endpoint: u8, buf: &mut [u8], timeout: Duration) -> libusb::Result<usize> {
+        self.handle.read_interrupt(endpoint, buf, timeout)
+    }
+
+    pub fn write_interrupt(&self, endpoint: u8, buf: &[u8], timeout: Duration) -> libusb::Result<usize> {
+        self.handle.write_interrupt(endpoint, buf, timeout)
+    }
+
+    pub fn read_product_string(
+        &self,
+        lang: Language,
+        desc: &DeviceDescriptor,
+        timeout: Duration,
+    ) -> libusb::Result<String> {
+        self.handle.read_product_string(lang, desc, timeout)
+    }
+
+    pub fn read_manufacturer_string(
+        &self,
+        lang: Language,
+        desc: &DeviceDescriptor,
+        timeout: Duration,
+    ) -> libusb::Result<String> {
+        self.handle.read_manufacturer_string(lang, desc, timeout)
+    }
+
+    pub fn read_serial_number_string(
+        &self,
+        lang: Language,
+        desc: &DeviceDescriptor,
+        timeout: Duration,
+    ) -> libusb::Result<String> {
+        self.handle.read_serial_number_string(lang, desc, timeout)
+    }
+
+    pub fn read_string_descriptor(&self, lang: Language, index: u8, timeout: Duration) -> libusb::Result<String> {
+        self.handle.read_string_descriptor(lang, index, timeout)
+    }
+
+    /// Isochronous transfers have no handshake or retry, so a write only reports how many bytes
+    /// libusb actually queued onto the bus for this (micro)frame - callers must not assume the
+    /// full buffer arrived at the far end.
+    pub fn write_iso(&self, endpoint: u8, buf: &[u8], timeout: Duration) -> libusb::Result<usize> {
+        unsafe { self.submit_single_iso_transfer(endpoint, buf.as_ptr() as *mut u8, buf.len(), timeout) }
+    }
+
+    /// Like [`write_iso`](Self::write_iso), only the actual-length of the single packet
+    /// transferred is returned; a short or zero read is not an error for isochronous endpoints.
+    pub fn read_iso(&self, endpoint: u8, buf: &mut [u8], timeout: Duration) -> libusb::Result<usize> {
+        unsafe { self.submit_single_iso_transfer(endpoint, buf.as_mut_ptr(), buf.len(), timeout) }
+    }
+
+    unsafe fn submit_single_iso_transfer(
+        &self,
+        endpoint: u8,
+        data: *mut u8,
+        len: usize,
+        timeout: Duration,
+    ) -> libusb::Result<usize> {
+        let done = std::sync::Arc::new(std::sync::atomic::AtomicBool::new(false));
+        let transfer = libusb_sys::libusb_alloc_transfer(1);
+
+        libusb_sys::libusb_fill_iso_transfer(
+            transfer,
+            self.handle.as_raw(),
+            endpoint,
+            data,
+            len as i32,
+            1,
+            iso_sync_callback,
+            std::sync::Arc::as_ptr(&done) as *mut std::os::raw::c_void,
+            timeout.as_millis() as u32,
+        );
+
+        libusb_sys::libusb_set_iso_packet_lengths(transfer, len as u32);
+
+        if libusb_sys::libusb_submit_transfer(transfer) != 0 {
+            libusb_sys::libusb_free_transfer(transfer);
+            return Err(libusb::Error::Other);
+        }
+
+        let deadline = std::time::Instant::now() + timeout;
+
+        while !done.load(std::sync::atomic::Ordering::SeqCst) && std::time::Instant::now() < deadline {
+            libusb_sys::libusb_handle_events(self.context.as_raw());
+        }
+
+        // Iso transfers routinely time out (packet loss is expected and tolerated), so this is a
+        // mainline path, not a rare edge case - libusb_cancel_transfer only requests cancellation,
+        // so the transfer must not be freed until its callback actually fires with a terminal
+        // status, same as run_bench_queued's cleanup.
+        if !done.load(std::sync::atomic::Ordering::SeqCst) {
+            libusb_sys::libusb_cancel_transfer(transfer);
+
+            while !done.load(std::sync::atomic::Ordering::SeqCst) {
+                libusb_sys::libusb_handle_events(self.context.as_raw());
+            }
+        }
+
+        let actual_length = (*transfer).iso_packet_desc[0].actual_length as usize;
+
+        libusb_sys::libusb_free_transfer(transfer);
+
+        Ok(actual_length)
+    }
+
+    fn sysfs_device_power_path(&self, attribute: &str) -> std::path::PathBuf {
+        let bus = self.handle.device().bus_number();
+        let address = self.handle.device().address();
+
+        std::path::PathBuf::from(format!(
+            "/sys/bus/usb/devices/usb{}/{}/power/{}",
+            bus, address, attribute
+        ))
+    }
+
+    /// `power/control`: the kernel's generic runtime-PM ABI, which only accepts `"on"` (force
+    /// active) or `"auto"` (let runtime PM suspend the device once idle).
+    pub fn sysfs_power_control_path(&self) -> std::path::PathBuf {
+        self.sysfs_device_power_path("control")
+    }
+
+    /// `power/autosuspend_delay_ms`: how long the device must be idle before runtime PM
+    /// actually suspends it once `power/control` is set to `"auto"`.
+    pub fn sysfs_autosuspend_delay_path(&self) -> std::path::PathBuf {
+        self.sysfs_device_power_path("autosuspend_delay_ms")
+    }
+
+    /// `power/runtime_status`: read-only, reports `"active"`, `"suspended"`, or a transitional
+    /// state - this is how we observe that a suspend/resume actually happened.
+    pub fn sysfs_runtime_status_path(&self) -> std::path::PathBuf {
+        self.sysfs_device_power_path("runtime_status")
+    }
+}
+
+extern "system" fn iso_sync_callback(transfer: *mut libusb_sys::libusb_transfer) {
+    unsafe {
+        let done = &*((*transfer).user_data as *const std::sync::atomic::AtomicBool);
+        done.store(true, std::sync::atomic::Ordering::SeqCst);
+    }
+}
@@ -2,12 +2,16 @@ use crate::device::*;
 use libusb::*;
 use rand::prelude::*;
 use std::fmt::Write;
+use std::os::raw::c_void;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::Arc;
 use std::time::{Duration, Instant};
 use usb_device::test_class;
 
 pub type TestFn = fn(&mut DeviceHandles, &mut String) -> ();
 
 const BENCH_TIMEOUT: Duration = Duration::from_secs(10);
+const QUEUE_DEPTH: usize = 8;
 
 macro_rules! tests {
     { $(fn $name:ident($dev:ident, $out:ident) $body:expr)* } => {
@@ -62,6 +66,87 @@ fn interface_name(dev, _out) {
         test_class::INTERFACE_DESCRIPTION);
 }
 
+fn ms_os_descriptors(dev, _out) {
+    const GET_DESCRIPTOR: u8 = 0x06;
+    const BOS_DESCRIPTOR_TYPE: u8 = 0x0F;
+    const MS_OS_20_FEATURE_COMPATIBLE_ID: u16 = 0x0003;
+    const MS_OS_20_FEATURE_REG_PROPERTY: u16 = 0x0004;
+
+    const MS_OS_20_UUID: [u8; 16] = [
+        0xDF, 0x60, 0xDD, 0xD8, 0x89, 0x45, 0xC7, 0x4C,
+        0x9C, 0xD2, 0x65, 0x9D, 0x9E, 0x64, 0x8A, 0x9F,
+    ];
+
+    let mut bos = [0u8; 255];
+
+    let bos_len = dev.read_control(
+        request_type(Direction::In, RequestType::Standard, Recipient::Device),
+        GET_DESCRIPTOR, (BOS_DESCRIPTOR_TYPE as u16) << 8, 0,
+        &mut bos, TIMEOUT).expect("read BOS descriptor");
+
+    let bos = &bos[..bos_len];
+
+    assert_eq!(bos[1], BOS_DESCRIPTOR_TYPE, "bDescriptorType");
+
+    let num_caps = bos[4];
+    let mut offset = 5;
+    let mut vendor_code = None;
+    let mut windows_version = None;
+    let mut descriptor_set_len = None;
+
+    for _ in 0..num_caps {
+        let cap_len = bos[offset] as usize;
+        let uuid = &bos[offset + 4..offset + 20];
+
+        if uuid == MS_OS_20_UUID {
+            windows_version = Some(u32::from_le_bytes(bos[offset + 20..offset + 24].try_into().unwrap()));
+            descriptor_set_len = Some(u16::from_le_bytes(bos[offset + 24..offset + 26].try_into().unwrap()));
+            vendor_code = Some(bos[offset + 26]);
+        }
+
+        offset += cap_len;
+    }
+
+    let vendor_code = vendor_code.expect("MS OS 2.0 platform capability descriptor");
+    let windows_version = windows_version.expect("dwWindowsVersion");
+    let descriptor_set_len = descriptor_set_len.expect("wMSOSDescriptorSetTotalLength");
+
+    assert!(windows_version > 0, "dwWindowsVersion");
+
+    let mut descriptor_set = vec![0u8; descriptor_set_len as usize];
+
+    assert_eq!(
+        dev.read_control(
+            request_type(Direction::In, RequestType::Vendor, Recipient::Device),
+            vendor_code, 0, 7,
+            &mut descriptor_set, TIMEOUT).expect("read MS OS 2.0 descriptor set"),
+        descriptor_set.len());
+
+    // Compatible ID feature descriptor: wLength(10..12), wDescriptorType(12..14),
+    // CompatibleID(14..22), SubCompatibleID(22..30, reserved).
+    assert_eq!(
+        u16::from_le_bytes(descriptor_set[12..14].try_into().unwrap()),
+        MS_OS_20_FEATURE_COMPATIBLE_ID, "bDescriptorType of compatible ID descriptor");
+
+    assert_eq!(
+        &descriptor_set[14..22],
+        test_class::MS_OS_20_COMPATIBLE_ID, "compatible ID");
+
+    let registry_property_offset = 30;
+
+    assert_eq!(
+        u16::from_le_bytes(descriptor_set[registry_property_offset + 2..registry_property_offset + 4].try_into().unwrap()),
+        MS_OS_20_FEATURE_REG_PROPERTY, "bDescriptorType of registry property descriptor");
+
+    let property_name_start = registry_property_offset + 4 + 2 + 2;
+    let property_data_len_start = property_name_start + test_class::MS_OS_20_REGISTRY_PROPERTY_NAME.len();
+    let property_data_start = property_data_len_start + 2;
+
+    assert_eq!(
+        &descriptor_set[property_data_start..property_data_start + test_class::MS_OS_20_REGISTRY_PROPERTY_DATA.len()],
+        test_class::MS_OS_20_REGISTRY_PROPERTY_DATA, "registry property data");
+}
+
 fn control_request(dev, _out) {
     let mut rng = rand::thread_rng();
 
@@ -173,6 +258,78 @@ fn bulk_loopback(dev, _out) {
     }
 }
 
+fn usbtmc_framing(dev, _out) {
+    const DEV_DEP_MSG_OUT: u8 = 1;
+    const REQUEST_DEV_DEP_MSG_IN: u8 = 2;
+    const DEV_DEP_MSG_IN: u8 = 2;
+    const EOM_BIT: u8 = 0x01;
+
+    dev.write_control(
+        request_type(Direction::Out, RequestType::Vendor, Recipient::Device),
+        test_class::REQ_SET_USBTMC_ENABLED,
+        1, 0,
+        &[], TIMEOUT).expect("enable USBTMC framing mode");
+
+    let max_packet_size = dev.ep_bulk_out_max_packet_size as usize;
+    let payload = random_data(max_packet_size * 2 + 17);
+    let b_tag: u8 = 1;
+
+    let mut remaining = &payload[..];
+
+    while !remaining.is_empty() {
+        let chunk_len = std::cmp::min(remaining.len(), max_packet_size - 12);
+        let (chunk, rest) = remaining.split_at(chunk_len);
+        let eom = rest.is_empty();
+
+        let mut packet = vec![0u8; 12 + chunk.len()];
+        packet[0] = DEV_DEP_MSG_OUT;
+        packet[1] = b_tag;
+        packet[2] = !b_tag;
+        packet[4..8].copy_from_slice(&(payload.len() as u32).to_le_bytes());
+        packet[8] = if eom { EOM_BIT } else { 0 };
+        packet[12..].copy_from_slice(chunk);
+
+        assert_eq!(
+            dev.write_bulk(dev.ep_bulk_out, &packet, TIMEOUT).expect("USBTMC bulk-OUT header+payload"),
+            packet.len());
+
+        remaining = rest;
+    }
+
+    let request = [
+        REQUEST_DEV_DEP_MSG_IN, b_tag, !b_tag, 0,
+        0, 0, 0, 0,
+        EOM_BIT, 0, 0, 0,
+    ];
+
+    assert_eq!(
+        dev.write_bulk(dev.ep_bulk_out, &request, TIMEOUT).expect("REQUEST_DEV_DEP_MSG_IN"),
+        request.len());
+
+    let padded_len = (payload.len() + 3) & !3;
+    let mut received = Vec::with_capacity(12 + padded_len);
+
+    loop {
+        let mut chunk = vec![0u8; max_packet_size];
+        let n = dev.read_bulk(dev.ep_bulk_in, &mut chunk, TIMEOUT).expect("USBTMC bulk-IN read");
+        received.extend_from_slice(&chunk[..n]);
+
+        if n < max_packet_size {
+            break;
+        }
+    }
+
+    assert_eq!(received[0], DEV_DEP_MSG_IN, "MsgID");
+    assert_eq!(received[1], b_tag, "bTag echo");
+    assert_eq!(received[2], !b_tag, "bTag inverse echo");
+    assert_eq!(
+        u32::from_le_bytes(received[4..8].try_into().unwrap()),
+        payload.len() as u32,
+        "TransferSize");
+    assert_eq!(received[8] & EOM_BIT, EOM_BIT, "EOM");
+    assert_eq!(&received[12..12 + payload.len()], &payload[..], "payload round-trip");
+}
+
 fn interrupt_loopback(dev, _out) {
     for len in &[0, 1, 2, 15, 31] {
         let data = random_data(*len);
@@ -215,6 +372,85 @@ fn bench_bulk_read(dev, out) {
     });
 }
 
+fn iso_loopback(dev, _out) {
+    let mut round_trips = 0;
+
+    for len in &[0, 1, 2, 63, 64, 128, dev.iso_packet_size as usize] {
+        let data = random_data(*len);
+
+        let sent = dev.write_iso(dev.ep_iso_out, &data, TIMEOUT)
+            .expect(&format!("iso write len {}", len));
+
+        if sent == 0 {
+            continue;
+        }
+
+        let mut response = vec![0u8; *len];
+
+        let received = match dev.read_iso(dev.ep_iso_in, &mut response, TIMEOUT) {
+            Ok(received) => received,
+            Err(_) => continue,
+        };
+
+        assert!(received <= data.len(), "iso read overrun len {}", len);
+        assert_eq!(&response[..received], &data[..received], "iso payload corruption len {}", len);
+
+        if received > 0 {
+            round_trips += 1;
+        }
+    }
+
+    assert!(round_trips > 0, "every iso packet was dropped, loopback was never actually exercised");
+}
+
+fn bench_iso(dev, out) {
+    run_bench_iso(dev, out, |data| {
+        dev.write_iso(dev.ep_iso_out, data, BENCH_TIMEOUT).unwrap_or(0)
+    });
+}
+
+fn bench_bulk_write_queued(dev, out) {
+    run_bench_queued(dev, out, dev.ep_bulk_out, TransferDirection::Write);
+}
+
+fn bench_bulk_read_queued(dev, out) {
+    run_bench_queued(dev, out, dev.ep_bulk_in, TransferDirection::Read);
+}
+
+fn suspend_resume_counter(dev, _out) {
+    if !suspend_supported() {
+        return;
+    }
+
+    let before = read_suspend_count(dev);
+
+    suspend_and_resume_port(dev).expect("suspend/resume the port");
+
+    let after = read_suspend_count(dev);
+
+    assert!(after > before, "expected suspend/resume count to increase, before {} after {}", before, after);
+}
+
+fn remote_wakeup(dev, _out) {
+    if !suspend_supported() {
+        return;
+    }
+
+    dev.write_control(
+        request_type(Direction::Out, RequestType::Vendor, Recipient::Device),
+        test_class::REQ_ARM_REMOTE_WAKEUP,
+        1, 0,
+        &[], TIMEOUT).expect("arm remote wakeup");
+
+    let resumed = suspend_port_until_remote_wakeup(dev, BENCH_TIMEOUT).expect("suspend pending remote wakeup");
+
+    assert!(resumed, "device did not signal remote wakeup within timeout");
+
+    let count = read_suspend_count(dev);
+
+    assert!(count > 0, "expected at least one suspend/resume cycle to be recorded");
+}
+
 }
 
 fn run_bench(dev: &DeviceHandles, out: &mut String, f: impl Fn(&mut [u8]) -> ()) {
@@ -252,8 +488,277 @@ fn run_bench(dev: &DeviceHandles, out: &mut String, f: impl Fn(&mut [u8]) -> ())
     .expect("write failed");
 }
 
+#[derive(Clone, Copy, PartialEq)]
+enum TransferDirection {
+    Write,
+    Read,
+}
+
+struct QueuedBenchState {
+    bytes_transferred: AtomicUsize,
+    errors: AtomicUsize,
+    shutting_down: AtomicBool,
+    outstanding: AtomicUsize,
+}
+
+// libusb only guarantees a transfer is safe to free once its callback has fired with a terminal
+// status - resubmitting unconditionally races libusb_free_transfer in run_bench_queued's cleanup,
+// since a resubmitted transfer is back in flight by the time the caller tries to free it. Once
+// shutdown is requested we let every transfer retire instead of resubmitting it, and track how
+// many are still outstanding so cleanup knows when it's actually safe to free them.
+extern "system" fn queued_bench_callback(transfer: *mut libusb_sys::libusb_transfer) {
+    unsafe {
+        let state = &*((*transfer).user_data as *const QueuedBenchState);
+
+        if (*transfer).status == libusb_sys::constants::LIBUSB_TRANSFER_COMPLETED {
+            state.bytes_transferred.fetch_add((*transfer).actual_length as usize, Ordering::SeqCst);
+        } else if (*transfer).status != libusb_sys::constants::LIBUSB_TRANSFER_CANCELLED {
+            state.errors.fetch_add(1, Ordering::SeqCst);
+        }
+
+        let resubmittable = matches!(
+            (*transfer).status,
+            libusb_sys::constants::LIBUSB_TRANSFER_COMPLETED | libusb_sys::constants::LIBUSB_TRANSFER_TIMED_OUT
+        );
+
+        if resubmittable && !state.shutting_down.load(Ordering::SeqCst) {
+            libusb_sys::libusb_submit_transfer(transfer);
+        } else {
+            state.outstanding.fetch_sub(1, Ordering::SeqCst);
+        }
+    }
+}
+
+fn run_bench_queued(dev: &DeviceHandles, out: &mut String, endpoint: u8, direction: TransferDirection) {
+    const TRANSFER_BYTES: usize = 64 * 1024;
+
+    dev.write_control(
+        request_type(Direction::Out, RequestType::Vendor, Recipient::Device),
+        test_class::REQ_SET_BENCH_ENABLED,
+        1,
+        0,
+        &[],
+        TIMEOUT,
+    )
+    .expect("enable bench mode");
+
+    let state = Arc::new(QueuedBenchState {
+        bytes_transferred: AtomicUsize::new(0),
+        errors: AtomicUsize::new(0),
+        shutting_down: AtomicBool::new(false),
+        outstanding: AtomicUsize::new(QUEUE_DEPTH),
+    });
+
+    let handle = dev.handle.as_raw();
+    let mut buffers: Vec<Vec<u8>> = (0..QUEUE_DEPTH).map(|_| random_data(TRANSFER_BYTES)).collect();
+    let mut transfers = Vec::with_capacity(QUEUE_DEPTH);
+    let start = Instant::now();
+
+    unsafe {
+        for buffer in buffers.iter_mut() {
+            let transfer = libusb_sys::libusb_alloc_transfer(0);
+
+            libusb_sys::libusb_fill_bulk_transfer(
+                transfer,
+                handle,
+                endpoint,
+                buffer.as_mut_ptr(),
+                buffer.len() as i32,
+                queued_bench_callback,
+                Arc::as_ptr(&state) as *mut c_void,
+                BENCH_TIMEOUT.as_millis() as u32,
+            );
+
+            libusb_sys::libusb_submit_transfer(transfer);
+            transfers.push(transfer);
+        }
+
+        while start.elapsed() < BENCH_TIMEOUT {
+            libusb_sys::libusb_handle_events(dev.context.as_raw());
+        }
+
+        state.shutting_down.store(true, Ordering::SeqCst);
+
+        for &transfer in &transfers {
+            libusb_sys::libusb_cancel_transfer(transfer);
+        }
+
+        // Cancellation only requests that the transfer stop; its callback must still fire with a
+        // terminal status before the transfer is safe to free.
+        let cleanup_deadline = Instant::now() + TIMEOUT;
+
+        while state.outstanding.load(Ordering::SeqCst) > 0 && Instant::now() < cleanup_deadline {
+            libusb_sys::libusb_handle_events(dev.context.as_raw());
+        }
+
+        if state.outstanding.load(Ordering::SeqCst) == 0 {
+            for transfer in transfers {
+                libusb_sys::libusb_free_transfer(transfer);
+            }
+        } else {
+            // Some callbacks never fired, so libusb may still use these transfers, their buffers
+            // and `state`: leak them rather than free memory that is still in use.
+            std::mem::forget(transfers);
+            std::mem::forget(buffers);
+            std::mem::forget(Arc::clone(&state));
+        }
+    }
+
+    let elapsed = start_elapsed(start.elapsed());
+    let bytes = state.bytes_transferred.load(Ordering::SeqCst);
+    let throughput = (bytes * 8) as f64 / 1_000_000.0 / elapsed;
+
+    writeln!(
+        out,
+        "  {:?}: {} bytes in {:.3}s -> {:.3}Mbit/s (queue depth {}, {} errors)",
+        direction, bytes, elapsed, throughput, QUEUE_DEPTH, state.errors.load(Ordering::SeqCst)
+    )
+    .expect("write failed");
+}
+
+impl std::fmt::Debug for TransferDirection {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TransferDirection::Write => write!(f, "write"),
+            TransferDirection::Read => write!(f, "read"),
+        }
+    }
+}
+
+fn start_elapsed(d: Duration) -> f64 {
+    d.as_secs() as f64 + (d.subsec_micros() as f64) * 0.000_001
+}
+
+fn run_bench_iso(dev: &DeviceHandles, out: &mut String, f: impl Fn(&mut [u8]) -> usize) {
+    const TRANSFERS: usize = 4096;
+
+    let packet_size = dev.iso_packet_size as usize;
+
+    dev.write_control(
+        request_type(Direction::Out, RequestType::Vendor, Recipient::Device),
+        test_class::REQ_SET_BENCH_ENABLED,
+        1,
+        0,
+        &[],
+        TIMEOUT,
+    )
+    .expect("enable bench mode");
+
+    let mut data = random_data(packet_size);
+    let mut confirmed_bytes = 0;
+
+    let start = Instant::now();
+
+    for _ in 0..TRANSFERS {
+        confirmed_bytes += f(&mut data);
+    }
+
+    let elapsed = start.elapsed();
+    let elapsed = elapsed.as_secs() as f64 + (elapsed.subsec_micros() as f64) * 0.000_001;
+    let throughput = (confirmed_bytes * 8) as f64 / 1_000_000.0 / elapsed;
+
+    writeln!(
+        out,
+        "  {} of {} packets confirmed, {} bytes in {:.3}s -> {:.3}Mbit/s (packet loss allowed)",
+        confirmed_bytes / packet_size.max(1), TRANSFERS, confirmed_bytes, elapsed, throughput
+    )
+    .expect("write failed");
+}
+
 fn random_data(len: usize) -> Vec<u8> {
     let mut data = vec![0u8; len];
     rand::thread_rng().fill(data.as_mut_slice());
     data
 }
+
+fn read_suspend_count(dev: &DeviceHandles) -> u32 {
+    let mut response = [0u8; 4];
+
+    dev.read_control(
+        request_type(Direction::In, RequestType::Vendor, Recipient::Device),
+        test_class::REQ_READ_SUSPEND_COUNT, 0, 0,
+        &mut response, TIMEOUT).expect("read suspend/resume counter");
+
+    u32::from_le_bytes(response)
+}
+
+#[cfg(target_os = "linux")]
+fn suspend_supported() -> bool {
+    true
+}
+
+#[cfg(not(target_os = "linux"))]
+fn suspend_supported() -> bool {
+    false
+}
+
+#[cfg(target_os = "linux")]
+fn wait_for_runtime_status(path: &std::path::Path, want: &str, timeout: Duration) -> std::io::Result<bool> {
+    let start = Instant::now();
+
+    while start.elapsed() < timeout {
+        if std::fs::read_to_string(path)?.trim() == want {
+            return Ok(true);
+        }
+
+        std::thread::sleep(Duration::from_millis(50));
+    }
+
+    Ok(false)
+}
+
+#[cfg(target_os = "linux")]
+fn suspend_and_resume_port(dev: &DeviceHandles) -> std::io::Result<()> {
+    let control_path = dev.sysfs_power_control_path();
+    let status_path = dev.sysfs_runtime_status_path();
+
+    std::fs::write(dev.sysfs_autosuspend_delay_path(), "0")?;
+    std::fs::write(&control_path, "auto")?;
+
+    if !wait_for_runtime_status(&status_path, "suspended", Duration::from_secs(2))? {
+        std::fs::write(&control_path, "on")?;
+        return Err(std::io::Error::new(std::io::ErrorKind::TimedOut, "device never reached runtime_status=suspended"));
+    }
+
+    std::fs::write(&control_path, "on")?;
+
+    if !wait_for_runtime_status(&status_path, "active", Duration::from_secs(2))? {
+        return Err(std::io::Error::new(std::io::ErrorKind::TimedOut, "device never reached runtime_status=active"));
+    }
+
+    Ok(())
+}
+
+#[cfg(not(target_os = "linux"))]
+fn suspend_and_resume_port(_dev: &DeviceHandles) -> std::io::Result<()> {
+    Err(std::io::Error::new(std::io::ErrorKind::Unsupported, "port suspend is only implemented on Linux"))
+}
+
+#[cfg(target_os = "linux")]
+fn suspend_port_until_remote_wakeup(dev: &DeviceHandles, timeout: Duration) -> std::io::Result<bool> {
+    let control_path = dev.sysfs_power_control_path();
+    let status_path = dev.sysfs_runtime_status_path();
+
+    std::fs::write(dev.sysfs_autosuspend_delay_path(), "0")?;
+    std::fs::write(&control_path, "auto")?;
+
+    if !wait_for_runtime_status(&status_path, "suspended", Duration::from_secs(2))? {
+        std::fs::write(&control_path, "on")?;
+        return Ok(false);
+    }
+
+    // The device itself, not us, is expected to drive the bus back to "active" via remote
+    // wakeup - unlike suspend_and_resume_port, we must not write "on" here before observing it.
+    let woken = wait_for_runtime_status(&status_path, "active", timeout)?;
+
+    if !woken {
+        std::fs::write(&control_path, "on")?;
+    }
+
+    Ok(woken)
+}
+
+#[cfg(not(target_os = "linux"))]
+fn suspend_port_until_remote_wakeup(_dev: &DeviceHandles, _timeout: Duration) -> std::io::Result<bool> {
+    Err(std::io::Error::new(std::io::ErrorKind::Unsupported, "port suspend is only implemented on Linux"))
+}